@@ -0,0 +1,611 @@
+use crate::error::{ContextFrame, Error, ErrorKind, Result};
+use std::collections::{HashMap, HashSet};
+use std::num::NonZeroUsize;
+
+/// Build an `ErrorKind::UnexpectedEof` asking for `n` more bytes than are currently
+/// available (`n` is clamped to at least 1, since zero bytes short isn't "missing" data).
+fn eof_needing(n: usize) -> ErrorKind {
+    ErrorKind::UnexpectedEof(nom::Needed::Size(
+        NonZeroUsize::new(n.max(1)).expect("n.max(1) is never zero"),
+    ))
+}
+
+/// A parsed FIT header or data message, as stashed inside a recoverable error so that
+/// parsing can resume from it.
+#[derive(Debug, Clone)]
+pub enum FitObject {
+    /// The file header.
+    Header(FitHeader),
+    /// A definition message was parsed; it updates decoder state but has no record to
+    /// hand back to the caller.
+    Definition,
+    /// A decoded data message.
+    Data(FitDataRecord),
+    /// The trailing file CRC was checked; nothing more remains to decode.
+    Eof,
+}
+
+/// The 12 or 14 byte header present at the start of every FIT file.
+#[derive(Debug, Clone)]
+pub struct FitHeader {
+    /// Size in bytes of the header.
+    pub size: u8,
+    /// Size in bytes of the data records, not including the header or the trailing CRC.
+    pub data_size: u32,
+}
+
+/// A single decoded data message (e.g. `record`, `session`, `field_description`).
+#[derive(Debug, Clone)]
+pub struct FitDataRecord {
+    /// The FIT global message name, e.g. `"record"`.
+    pub kind: String,
+    /// The fields present in this message, in definition order.
+    pub fields: Vec<FitDataField>,
+}
+
+/// A single field inside a [`FitDataRecord`], holding its raw, not-yet-typed bytes as
+/// recorded in the file (interpreting them against the FIT profile's base types is left
+/// to the caller).
+#[derive(Debug, Clone)]
+pub struct FitDataField {
+    /// The field's definition number.
+    pub number: u8,
+    /// `Some(dev_data_index)` if this is a developer field, `None` for a profile field.
+    pub dev_data_index: Option<u8>,
+    /// The field's raw bytes, in the endianness the definition message declared.
+    pub value: Vec<u8>,
+}
+
+/// Options controlling how lenient [`from_bytes_with_options`] is when it encounters a
+/// recoverable error.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    /// If `true`, a CRC mismatch does not abort parsing; the record or header that failed
+    /// its CRC check is kept and parsing resumes from where it left off.
+    pub ignore_crc_errors: bool,
+    /// If `true`, bytes remaining after the documented data size has been consumed are
+    /// ignored instead of raising [`ErrorKind::TrailingBytes`].
+    pub allow_trailing_bytes: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Endian {
+    Little,
+    Big,
+}
+
+#[derive(Debug, Clone)]
+struct FieldDef {
+    number: u8,
+    size: u8,
+}
+
+#[derive(Debug, Clone)]
+struct DevFieldDef {
+    field_num: u8,
+    size: u8,
+    dev_data_index: u8,
+}
+
+#[derive(Debug, Clone)]
+struct MessageDefinition {
+    global_number: u16,
+    #[allow(dead_code)]
+    endian: Endian,
+    fields: Vec<FieldDef>,
+    dev_fields: Vec<DevFieldDef>,
+}
+
+/// Decoder state that must persist across record boundaries within a file: the
+/// definition-message table, developer field descriptions, and the running CRC.
+#[derive(Debug, Default)]
+pub(crate) struct DecoderState {
+    header: Option<FitHeader>,
+    /// Definition messages, keyed by local message type (0-15).
+    definitions: HashMap<u8, MessageDefinition>,
+    /// `(developer_data_index, field_definition_number)` pairs announced by a
+    /// `field_description` (global number 206) message seen so far.
+    developer_field_descriptions: HashSet<(u8, u8)>,
+    /// CRC-16 accumulated over every header and record byte consumed so far.
+    crc: u16,
+    /// Bytes of the record stream (excluding the header and trailing CRC) consumed so far.
+    consumed: usize,
+    /// Whether the trailing file CRC has already been checked, so that once data_size
+    /// bytes are consumed we report `TrailingBytes` rather than re-reading the CRC.
+    trailer_checked: bool,
+}
+
+/// Deserialize a FIT file, aborting on the first error encountered.
+pub fn from_bytes(data: &[u8]) -> Result<Vec<FitDataRecord>> {
+    from_bytes_with_options(data, &ParseOptions::default()).map(|(records, _)| records)
+}
+
+/// Deserialize a FIT file using `options` to decide whether recoverable errors
+/// (`InvalidCrc`, `TrailingBytes`) should be salvaged instead of aborting.
+///
+/// On success, returns the decoded records alongside any recoverable errors that were
+/// suppressed along the way. A fatal error still aborts decoding immediately.
+pub fn from_bytes_with_options(
+    data: &[u8],
+    options: &ParseOptions,
+) -> Result<(Vec<FitDataRecord>, Vec<Error>)> {
+    let mut state = DecoderState::default();
+    let mut records = Vec::new();
+    let mut suppressed = Vec::new();
+    let mut remaining = data;
+
+    loop {
+        if remaining.is_empty() {
+            break;
+        }
+        match decode_next(&mut state, remaining) {
+            Ok((obj, rest)) => {
+                if let FitObject::Data(record) = obj {
+                    records.push(record);
+                }
+                remaining = rest;
+            }
+            Err(err) => match (err.severity(), &*err) {
+                (crate::error::Severity::Recoverable, ErrorKind::InvalidCrc((rest, obj, ..)))
+                    if options.ignore_crc_errors =>
+                {
+                    if let FitObject::Data(record) = obj.clone() {
+                        records.push(record);
+                    }
+                    remaining = &data[data.len() - rest.len()..];
+                    suppressed.push(err);
+                }
+                (crate::error::Severity::Recoverable, ErrorKind::TrailingBytes(_))
+                    if options.allow_trailing_bytes =>
+                {
+                    suppressed.push(err);
+                    break;
+                }
+                _ => return Err(err),
+            },
+        }
+    }
+
+    Ok((records, suppressed))
+}
+
+/// The result of [`from_bytes_collecting`]: every record that was successfully decoded,
+/// plus every recoverable problem that was hit and skipped along the way.
+#[derive(Debug, Default)]
+pub struct ParseOutcome {
+    /// Records that were successfully decoded.
+    pub records: Vec<FitDataRecord>,
+    /// Every error that was collected instead of aborting, in the order it was hit: the
+    /// recoverable ones plus a `MissingDefinitionMessage`, which [`ErrorKind::severity`]
+    /// classifies `Fatal` but [`from_bytes_collecting`] records rather than aborting on.
+    pub errors: Vec<Error>,
+}
+
+/// Parse the whole file without aborting on the first error, recording every recoverable
+/// problem (`InvalidCrc`, `TrailingBytes`, a `MissingDefinitionMessage` for an unknown
+/// local message) instead of bailing out. A fatal error still aborts immediately.
+///
+/// `MissingDefinitionMessage` is classified `Fatal` by [`ErrorKind::severity`] in general,
+/// since without a definition there is no way to know how many bytes the unknown message
+/// occupies and therefore no safe place to resume mid-file. This entry point special-cases
+/// it anyway: the record is skipped by treating the rest of the file as unparseable (the
+/// same way an unrecoverable `TrailingBytes` tail is handled), rather than aborting outright.
+///
+/// This is meant for tooling that needs a complete problem list for a file in one pass,
+/// e.g. validating a whole directory of activity files.
+pub fn from_bytes_collecting(data: &[u8]) -> Result<ParseOutcome> {
+    let mut state = DecoderState::default();
+    let mut outcome = ParseOutcome::default();
+    let mut remaining = data;
+
+    while !remaining.is_empty() {
+        match decode_next(&mut state, remaining) {
+            Ok((obj, rest)) => {
+                if let FitObject::Data(record) = obj {
+                    outcome.records.push(record);
+                }
+                remaining = rest;
+            }
+            Err(err) => {
+                let collectible = err.is_recoverable()
+                    || matches!(&*err, ErrorKind::MissingDefinitionMessage(..));
+                if !collectible {
+                    return Err(err);
+                }
+                if let ErrorKind::InvalidCrc((rest, obj, ..)) = &*err {
+                    if let FitObject::Data(record) = obj.clone() {
+                        outcome.records.push(record);
+                    }
+                    remaining = &data[data.len() - rest.len()..];
+                } else {
+                    // `TrailingBytes` and an unresolvable `MissingDefinitionMessage`: there
+                    // is no byte offset we can safely resume from, so the rest of the file
+                    // is left unparsed rather than guessed at.
+                    remaining = &[];
+                }
+                outcome.errors.push(err);
+            }
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// Decode the next `FitObject` (header, definition, data message, or trailing CRC) from
+/// the front of `data`, returning it along with the unconsumed remainder. This is the
+/// single entry point every public decode function in this module and
+/// [`crate::StreamingFitDecoder`] funnels through; `state` carries everything that must
+/// persist across calls (definition table, developer field descriptions, running CRC).
+pub(crate) fn decode_next<'d>(
+    state: &mut DecoderState,
+    data: &'d [u8],
+) -> Result<(FitObject, &'d [u8])> {
+    if state.header.is_none() {
+        decode_header(state, data)
+    } else {
+        decode_record(state, data)
+    }
+}
+
+fn decode_header<'d>(state: &mut DecoderState, data: &'d [u8]) -> Result<(FitObject, &'d [u8])> {
+    if data.len() < 12 {
+        return Err(eof_needing(12 - data.len()).into());
+    }
+    let header_size = data[0] as usize;
+    if header_size < 12 {
+        return Err(ErrorKind::ParseError(0, nom::error::ErrorKind::Tag).into());
+    }
+    if data.len() < header_size {
+        return Err(eof_needing(header_size - data.len()).into());
+    }
+    if &data[8..12] != b".FIT" {
+        return Err(ErrorKind::ParseError(8, nom::error::ErrorKind::Tag).into());
+    }
+
+    let data_size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+    let header = FitHeader {
+        size: header_size as u8,
+        data_size,
+    };
+
+    state.crc = crc16(&data[..header_size], 0);
+    state.header = Some(header.clone());
+    state.consumed = 0;
+
+    Ok((FitObject::Header(header), &data[header_size..]))
+}
+
+fn decode_record<'d>(state: &mut DecoderState, data: &'d [u8]) -> Result<(FitObject, &'d [u8])> {
+    let data_size = state
+        .header
+        .as_ref()
+        .expect("header decoded first")
+        .data_size as usize;
+
+    if state.consumed >= data_size {
+        if !state.trailer_checked {
+            return decode_trailer(state, data);
+        }
+        return Err(ErrorKind::TrailingBytes(data.len()).into());
+    }
+
+    if data.is_empty() {
+        return Err(eof_needing(1).into());
+    }
+
+    let record_header = data[0];
+    if record_header & 0x40 != 0 {
+        decode_definition(state, data, record_header)
+    } else {
+        decode_data(state, data, record_header)
+    }
+}
+
+fn decode_trailer<'d>(state: &mut DecoderState, data: &'d [u8]) -> Result<(FitObject, &'d [u8])> {
+    if data.len() < 2 {
+        return Err(eof_needing(2 - data.len()).into());
+    }
+    let expected = u16::from_le_bytes([data[0], data[1]]);
+    let rest = &data[2..];
+    state.trailer_checked = true;
+
+    if expected != state.crc {
+        return Err(
+            ErrorKind::InvalidCrc((rest.to_vec(), FitObject::Eof, expected, state.crc)).into(),
+        );
+    }
+    Ok((FitObject::Eof, rest))
+}
+
+fn decode_definition<'d>(
+    state: &mut DecoderState,
+    data: &'d [u8],
+    record_header: u8,
+) -> Result<(FitObject, &'d [u8])> {
+    let local_type = record_header & 0x0F;
+    let has_dev_fields = record_header & 0x20 != 0;
+
+    if data.len() < 6 {
+        return Err(eof_needing(6 - data.len()).into());
+    }
+    let endian = if data[2] == 1 {
+        Endian::Big
+    } else {
+        Endian::Little
+    };
+    let global_number = match endian {
+        Endian::Little => u16::from_le_bytes([data[3], data[4]]),
+        Endian::Big => u16::from_be_bytes([data[3], data[4]]),
+    };
+    let num_fields = data[5] as usize;
+
+    let mut offset = 6;
+    if data.len() < offset + num_fields * 3 {
+        return Err(eof_needing(offset + num_fields * 3 - data.len()).into());
+    }
+    let mut fields = Vec::with_capacity(num_fields);
+    for i in 0..num_fields {
+        let base = offset + i * 3;
+        fields.push(FieldDef {
+            number: data[base],
+            size: data[base + 1],
+        });
+    }
+    offset += num_fields * 3;
+
+    let mut dev_fields = Vec::new();
+    if has_dev_fields {
+        if data.len() < offset + 1 {
+            return Err(eof_needing(offset + 1 - data.len()).into());
+        }
+        let num_dev_fields = data[offset] as usize;
+        offset += 1;
+        if data.len() < offset + num_dev_fields * 3 {
+            return Err(eof_needing(offset + num_dev_fields * 3 - data.len()).into());
+        }
+        for i in 0..num_dev_fields {
+            let base = offset + i * 3;
+            let field_num = data[base];
+            let size = data[base + 1];
+            let dev_data_index = data[base + 2];
+            if !state
+                .developer_field_descriptions
+                .contains(&(dev_data_index, field_num))
+            {
+                return Err(ErrorKind::MissingDeveloperDefinitionMessage().into());
+            }
+            dev_fields.push(DevFieldDef {
+                field_num,
+                size,
+                dev_data_index,
+            });
+        }
+        offset += num_dev_fields * 3;
+    }
+
+    state.crc = crc16(&data[..offset], state.crc);
+    state.consumed += offset;
+    state.definitions.insert(
+        local_type,
+        MessageDefinition {
+            global_number,
+            endian,
+            fields,
+            dev_fields,
+        },
+    );
+
+    Ok((FitObject::Definition, &data[offset..]))
+}
+
+fn decode_data<'d>(
+    state: &mut DecoderState,
+    data: &'d [u8],
+    record_header: u8,
+) -> Result<(FitObject, &'d [u8])> {
+    let local_type = record_header & 0x0F;
+    let record_start = state.consumed;
+
+    let definition = match state.definitions.get(&local_type) {
+        Some(def) => def.clone(),
+        None => return Err(ErrorKind::MissingDefinitionMessage(local_type, record_start).into()),
+    };
+    let name = message_name(definition.global_number);
+
+    let field_specs = definition
+        .fields
+        .iter()
+        .map(|f| (f.number, f.size, None::<u8>))
+        .chain(
+            definition
+                .dev_fields
+                .iter()
+                .map(|f| (f.field_num, f.size, Some(f.dev_data_index))),
+        );
+
+    let mut offset = 1usize;
+    let mut fields = Vec::with_capacity(definition.fields.len() + definition.dev_fields.len());
+    for (number, size, dev_data_index) in field_specs {
+        let end = offset + size as usize;
+        if data.len() < end {
+            let record_len = 1
+                + definition.fields.iter().map(|f| f.size as usize).sum::<usize>()
+                + definition.dev_fields.iter().map(|f| f.size as usize).sum::<usize>();
+            let err = eof_needing(end - data.len())
+                .context(ContextFrame::Field(number))
+                .context(ContextFrame::Message(
+                    local_type,
+                    definition.global_number,
+                    name,
+                ))
+                .context(ContextFrame::Record(record_start, record_start + record_len));
+            return Err(err.into());
+        }
+        fields.push(FitDataField {
+            number,
+            dev_data_index,
+            value: data[offset..end].to_vec(),
+        });
+        offset = end;
+    }
+
+    state.crc = crc16(&data[..offset], state.crc);
+    state.consumed += offset;
+
+    let record = FitDataRecord {
+        kind: name.to_string(),
+        fields,
+    };
+    if definition.global_number == 206 {
+        register_field_description(state, &record);
+    }
+
+    Ok((FitObject::Data(record), &data[offset..]))
+}
+
+/// Record a `field_description` (global message 206) message's
+/// `(developer_data_index, field_definition_number)` so later definition messages that
+/// reference it pass the `MissingDeveloperDefinitionMessage` check.
+fn register_field_description(state: &mut DecoderState, record: &FitDataRecord) {
+    let dev_data_index = record
+        .fields
+        .iter()
+        .find(|f| f.number == 0)
+        .and_then(|f| f.value.first().copied());
+    let field_definition_number = record
+        .fields
+        .iter()
+        .find(|f| f.number == 1)
+        .and_then(|f| f.value.first().copied());
+    if let (Some(idx), Some(num)) = (dev_data_index, field_definition_number) {
+        state.developer_field_descriptions.insert((idx, num));
+    }
+}
+
+/// The handful of FIT global message names this decoder recognizes; anything else is
+/// surfaced as `"unknown"` since interpreting the full FIT profile is out of scope here.
+fn message_name(global_number: u16) -> &'static str {
+    match global_number {
+        0 => "file_id",
+        18 => "session",
+        19 => "lap",
+        20 => "record",
+        21 => "event",
+        23 => "device_info",
+        206 => "field_description",
+        _ => "unknown",
+    }
+}
+
+/// The FIT SDK's CRC-16 algorithm (polynomial 0xA001, processed 4 bits at a time via a
+/// 16-entry lookup table), folding `bytes` into a `seed` accumulator.
+pub(crate) fn crc16(bytes: &[u8], seed: u16) -> u16 {
+    const TABLE: [u16; 16] = [
+        0x0000, 0xCC01, 0xD801, 0x1400, 0xF001, 0x3C00, 0x2800, 0xE401, 0xA001, 0x6C00, 0x7800,
+        0xB401, 0x5000, 0x9C01, 0x8801, 0x4400,
+    ];
+    let mut crc = seed;
+    for &byte in bytes {
+        let mut tmp = TABLE[(crc & 0xF) as usize];
+        crc = (crc >> 4) & 0x0FFF;
+        crc ^= tmp ^ TABLE[(byte as u16 & 0xF) as usize];
+
+        tmp = TABLE[(crc & 0xF) as usize];
+        crc = (crc >> 4) & 0x0FFF;
+        crc ^= tmp ^ TABLE[((byte as u16 >> 4) & 0xF) as usize];
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a minimal one-record FIT file: header, a `record` (global 20) definition
+    /// with a single 1-byte field, its data message, and a correct trailing CRC.
+    fn build_fit_file(field_value: u8) -> Vec<u8> {
+        let mut body = Vec::new();
+        // Definition message: local type 0, global message 20 ("record"), one u8 field.
+        body.extend_from_slice(&[0x40, 0x00, 0x00, 20, 0x00, 0x01, 0x02, 0x01, 0x02]);
+        // Data message: local type 0, one field byte.
+        body.extend_from_slice(&[0x00, field_value]);
+
+        let mut header = vec![12u8, 0x10, 0x00, 0x00];
+        header.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        header.extend_from_slice(b".FIT");
+
+        let mut file = header.clone();
+        file.extend_from_slice(&body);
+
+        let mut crc = crc16(&header[..12], 0);
+        crc = crc16(&body, crc);
+        file.extend_from_slice(&crc.to_le_bytes());
+        file
+    }
+
+    #[test]
+    fn decodes_a_minimal_file() {
+        let file = build_fit_file(42);
+        let records = from_bytes(&file).expect("valid file should decode");
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].kind, "record");
+        assert_eq!(records[0].fields.len(), 1);
+        assert_eq!(records[0].fields[0].value, vec![42]);
+    }
+
+    #[test]
+    fn rejects_a_bad_crc_by_default() {
+        let mut file = build_fit_file(42);
+        let last = file.len() - 1;
+        file[last] ^= 0xFF;
+        let err = from_bytes(&file).unwrap_err();
+        assert!(matches!(*err, ErrorKind::InvalidCrc(..)));
+        assert!(err.is_recoverable());
+    }
+
+    #[test]
+    fn ignore_crc_errors_salvages_the_records() {
+        let mut file = build_fit_file(42);
+        let last = file.len() - 1;
+        file[last] ^= 0xFF;
+        let (records, suppressed) = from_bytes_with_options(
+            &file,
+            &ParseOptions {
+                ignore_crc_errors: true,
+                allow_trailing_bytes: false,
+            },
+        )
+        .expect("crc mismatch should be suppressed");
+        assert_eq!(records.len(), 1);
+        assert_eq!(suppressed.len(), 1);
+    }
+
+    #[test]
+    fn unknown_local_message_is_collected_and_skipped() {
+        let mut file = build_fit_file(42);
+        // Flip the data message's local type so it no longer matches any definition.
+        let record_start = 12 + 9; // header + definition message
+        file[record_start] = 0x05;
+        // Recomputing the trailing CRC isn't possible without knowing where the file
+        // becomes unparseable, so `from_bytes_collecting` must stop at the bad record
+        // rather than needing a valid CRC for bytes after it.
+        let outcome = from_bytes_collecting(&file).expect("collecting mode never aborts here");
+        assert_eq!(outcome.records.len(), 0);
+        assert_eq!(outcome.errors.len(), 1);
+        assert!(matches!(
+            *outcome.errors[0],
+            ErrorKind::MissingDefinitionMessage(..)
+        ));
+    }
+
+    #[test]
+    fn truncated_field_reports_message_and_field_context() {
+        let file = build_fit_file(42);
+        // Cut the file off mid data-message, before its one field byte arrives.
+        let truncated = &file[..file.len() - 3];
+        let err = from_bytes(truncated).unwrap_err();
+        let rendered = err.to_string();
+        assert!(rendered.contains("field 2"));
+        assert!(rendered.contains("record"));
+    }
+}
@@ -32,6 +32,86 @@ pub enum ErrorKind {
     ValueError(String),
     /// Developer fields must be defined before they can be mentioned
     MissingDeveloperDefinitionMessage(),
+    /// Wraps another error with a stack of breadcrumbs describing where in the FIT
+    /// structure it occurred, innermost frame first.
+    Context(Box<ErrorKind>, Vec<ContextFrame>),
+}
+
+/// A single breadcrumb describing where in the FIT structure a decode failure happened,
+/// pushed onto an error as it bubbles up out of the message/field it occurred in.
+#[derive(Debug, Clone)]
+pub enum ContextFrame {
+    /// The byte range of the record that was being decoded.
+    Record(usize, usize),
+    /// The local/global message number and name of the message being decoded.
+    Message(u8, u16, &'static str),
+    /// The field definition number of the field being decoded.
+    Field(u8),
+}
+
+impl fmt::Display for ContextFrame {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContextFrame::Record(start, end) => write!(fmt, "bytes {:#x}..{:#x}", start, end),
+            ContextFrame::Message(local_number, global_number, name) => write!(
+                fmt,
+                "message `{}` (local {}, global {})",
+                name, local_number, global_number
+            ),
+            ContextFrame::Field(number) => write!(fmt, "field {}", number),
+        }
+    }
+}
+
+/// Whether an error can be salvaged from and parsing resumed, or whether it is fatal and
+/// parsing cannot continue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Parsing can resume, optionally using data stashed inside the error itself.
+    Recoverable,
+    /// Parsing cannot continue past this point.
+    Fatal,
+}
+
+impl ErrorKind {
+    /// Classify this error as recoverable (parsing may be resumed) or fatal (it may not).
+    ///
+    /// `InvalidCrc` and `TrailingBytes` are recoverable because the data needed to resume
+    /// (the already-decoded `FitObject` and/or the remaining bytes) is available to the
+    /// caller. `Context` takes the severity of the error it wraps. Everything else,
+    /// including `Io` and `UnexpectedEof`, is fatal because there is no sensible position
+    /// to resume from.
+    pub fn severity(&self) -> Severity {
+        match self {
+            ErrorKind::InvalidCrc(..) => Severity::Recoverable,
+            ErrorKind::TrailingBytes(_) => Severity::Recoverable,
+            ErrorKind::Io(_) => Severity::Fatal,
+            ErrorKind::MissingDefinitionMessage(..) => Severity::Fatal,
+            ErrorKind::ParseError(..) => Severity::Fatal,
+            ErrorKind::UnexpectedEof(..) => Severity::Fatal,
+            ErrorKind::ValueError(..) => Severity::Fatal,
+            ErrorKind::MissingDeveloperDefinitionMessage() => Severity::Fatal,
+            ErrorKind::Context(inner, _) => inner.severity(),
+        }
+    }
+
+    /// Shorthand for `self.severity() == Severity::Recoverable`.
+    pub fn is_recoverable(&self) -> bool {
+        self.severity() == Severity::Recoverable
+    }
+
+    /// Push a breadcrumb onto this error, wrapping it in `ErrorKind::Context` if it isn't
+    /// already one. Called on the error branch as decoding unwinds out of a message or
+    /// field, so the low-overhead success path never builds context.
+    pub fn context(self, frame: ContextFrame) -> ErrorKind {
+        match self {
+            ErrorKind::Context(inner, mut frames) => {
+                frames.push(frame);
+                ErrorKind::Context(inner, frames)
+            }
+            other => ErrorKind::Context(Box::new(other), vec![frame]),
+        }
+    }
 }
 
 impl StdError for ErrorKind {
@@ -45,6 +125,10 @@ impl StdError for ErrorKind {
             ErrorKind::UnexpectedEof(..) => None,
             ErrorKind::ValueError(..) => None,
             ErrorKind::MissingDeveloperDefinitionMessage(..) => None,
+            ErrorKind::Context(ref inner, _) => match **inner {
+                ErrorKind::Io(ref err) => Some(err),
+                _ => None,
+            },
         }
     }
 }
@@ -95,6 +179,42 @@ impl fmt::Display for ErrorKind {
             ErrorKind::MissingDeveloperDefinitionMessage() => {
                 write!(fmt, "developer field referenced before being defined")
             }
+            ErrorKind::Context(inner, frames) => {
+                write!(fmt, "{}", inner)?;
+                for frame in frames {
+                    write!(fmt, "\n  while decoding {}", frame)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_wraps_without_losing_severity() {
+        let err = ErrorKind::TrailingBytes(3).context(ContextFrame::Field(5));
+        assert_eq!(err.severity(), Severity::Recoverable);
+        assert!(err.is_recoverable());
+    }
+
+    #[test]
+    fn repeated_context_accumulates_innermost_first() {
+        let err = ErrorKind::MissingDeveloperDefinitionMessage()
+            .context(ContextFrame::Field(5))
+            .context(ContextFrame::Message(0, 20, "record"))
+            .context(ContextFrame::Record(0x10, 0x20));
+        match &err {
+            ErrorKind::Context(_, frames) => assert_eq!(frames.len(), 3),
+            _ => panic!("expected a Context error"),
         }
+        let rendered = err.to_string();
+        assert!(rendered.contains("developer field referenced before being defined"));
+        assert!(rendered.contains("field 5"));
+        assert!(rendered.contains("message `record` (local 0, global 20)"));
+        assert!(rendered.contains("bytes 0x10..0x20"));
     }
 }
@@ -0,0 +1,157 @@
+use crate::error::ErrorKind;
+use std::os::raw::c_char;
+
+/// A stable, numerically-fixed error code for `ErrorKind`, suitable for crossing an FFI
+/// boundary where downstream callers can't depend on the `nom` types embedded in several
+/// `ErrorKind` variants.
+///
+/// The numeric values are part of the ABI: existing entries must never be renumbered, and
+/// new variants must only ever be appended.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FitErrorCode {
+    /// Maps to `ErrorKind::InvalidCrc`.
+    InvalidCrc = 0,
+    /// Maps to `ErrorKind::Io`.
+    Io = 1,
+    /// Maps to `ErrorKind::MissingDefinitionMessage`.
+    MissingDefinitionMessage = 2,
+    /// Maps to `ErrorKind::TrailingBytes`.
+    TrailingBytes = 3,
+    /// Maps to `ErrorKind::ParseError`.
+    ParseError = 4,
+    /// Maps to `ErrorKind::UnexpectedEof`.
+    UnexpectedEof = 5,
+    /// Maps to `ErrorKind::ValueError`.
+    ValueError = 6,
+    /// Maps to `ErrorKind::MissingDeveloperDefinitionMessage`.
+    MissingDeveloperDefinitionMessage = 7,
+}
+
+impl ErrorKind {
+    /// The stable `FitErrorCode` for this error, for use by non-Rust callers.
+    ///
+    /// `ErrorKind::Context` reports the code of the error it wraps, since context frames
+    /// are a Rust-side debugging aid with no ABI representation of their own.
+    pub fn code(&self) -> FitErrorCode {
+        match self {
+            ErrorKind::InvalidCrc(..) => FitErrorCode::InvalidCrc,
+            ErrorKind::Io(_) => FitErrorCode::Io,
+            ErrorKind::MissingDefinitionMessage(..) => FitErrorCode::MissingDefinitionMessage,
+            ErrorKind::TrailingBytes(_) => FitErrorCode::TrailingBytes,
+            ErrorKind::ParseError(..) => FitErrorCode::ParseError,
+            ErrorKind::UnexpectedEof(..) => FitErrorCode::UnexpectedEof,
+            ErrorKind::ValueError(..) => FitErrorCode::ValueError,
+            ErrorKind::MissingDeveloperDefinitionMessage() => {
+                FitErrorCode::MissingDeveloperDefinitionMessage
+            }
+            ErrorKind::Context(inner, _) => inner.code(),
+        }
+    }
+
+    /// The byte offset at which this error occurred, if one is available.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            ErrorKind::MissingDefinitionMessage(_, pos) => Some(*pos),
+            ErrorKind::TrailingBytes(pos) => Some(*pos),
+            ErrorKind::ParseError(pos, _) => Some(*pos),
+            ErrorKind::Context(inner, _) => inner.offset(),
+            _ => None,
+        }
+    }
+}
+
+/// Static, NUL-terminated messages returned by [`fit_error_message`], one per
+/// `FitErrorCode` variant in declaration order.
+static MESSAGES: [&str; 8] = [
+    "CRC value did not match\0",
+    "io error\0",
+    "no definition found for local message number\0",
+    "bytes remain past expected EOF location\0",
+    "parser error\0",
+    "parser error: requires more data\0",
+    "value error\0",
+    "developer field referenced before being defined\0",
+];
+
+/// Return the stable code for `error`, writing its byte offset (or `0` if it has none)
+/// into `*offset_out`. Pass a null `offset_out` to skip the offset.
+///
+/// # Safety
+/// `error` must be a valid, non-null pointer to an `ErrorKind` owned by the caller, and
+/// `offset_out`, if non-null, must point to writable memory for one `usize`.
+#[no_mangle]
+pub unsafe extern "C" fn fit_error_code(
+    error: *const ErrorKind,
+    offset_out: *mut usize,
+) -> FitErrorCode {
+    let error = &*error;
+    if !offset_out.is_null() {
+        *offset_out = error.offset().unwrap_or(0);
+    }
+    error.code()
+}
+
+/// Return a pointer to a static, NUL-terminated, UTF-8 message describing `code`.
+///
+/// The returned pointer is valid for the lifetime of the program and must not be freed
+/// by the caller.
+#[no_mangle]
+pub extern "C" fn fit_error_message(code: FitErrorCode) -> *const c_char {
+    MESSAGES[code as usize].as_ptr() as *const c_char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ContextFrame;
+
+    #[test]
+    fn code_maps_trailing_bytes_and_missing_developer_definition() {
+        assert_eq!(
+            ErrorKind::TrailingBytes(4).code(),
+            FitErrorCode::TrailingBytes
+        );
+        assert_eq!(
+            ErrorKind::MissingDeveloperDefinitionMessage().code(),
+            FitErrorCode::MissingDeveloperDefinitionMessage
+        );
+    }
+
+    #[test]
+    fn context_delegates_code_and_offset_to_the_wrapped_error() {
+        let err = ErrorKind::MissingDefinitionMessage(2, 0x40).context(ContextFrame::Field(1));
+        assert_eq!(err.code(), FitErrorCode::MissingDefinitionMessage);
+        assert_eq!(err.offset(), Some(0x40));
+    }
+
+    #[test]
+    fn every_code_has_a_nul_terminated_message() {
+        let codes = [
+            FitErrorCode::InvalidCrc,
+            FitErrorCode::Io,
+            FitErrorCode::MissingDefinitionMessage,
+            FitErrorCode::TrailingBytes,
+            FitErrorCode::ParseError,
+            FitErrorCode::UnexpectedEof,
+            FitErrorCode::ValueError,
+            FitErrorCode::MissingDeveloperDefinitionMessage,
+        ];
+        for code in codes {
+            let msg = fit_error_message(code);
+            assert!(!msg.is_null());
+            unsafe {
+                assert!(std::ffi::CStr::from_ptr(msg).to_str().is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn fit_error_code_writes_the_offset_out_param() {
+        let err: ErrorKind = ErrorKind::TrailingBytes(7);
+        let mut offset = 0usize;
+        let code = unsafe { fit_error_code(&err, &mut offset) };
+        assert_eq!(code, FitErrorCode::TrailingBytes);
+        assert_eq!(offset, 7);
+    }
+}
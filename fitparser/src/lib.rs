@@ -0,0 +1,12 @@
+mod de;
+mod error;
+mod ffi;
+mod streaming;
+
+pub use de::{
+    from_bytes, from_bytes_collecting, from_bytes_with_options, FitDataField, FitDataRecord,
+    FitObject, ParseOptions, ParseOutcome,
+};
+pub use error::{ContextFrame, Error, ErrorKind, Result, Severity};
+pub use ffi::{fit_error_code, fit_error_message, FitErrorCode};
+pub use streaming::StreamingFitDecoder;
@@ -0,0 +1,154 @@
+use crate::de::{decode_next, DecoderState, FitDataRecord, FitObject};
+use crate::error::{ErrorKind, Result};
+
+/// Drill through any `ErrorKind::Context` wrapping to the error underneath, so waiting on
+/// `UnexpectedEof` still works once the decoder starts attaching breadcrumbs to it.
+fn innermost(err: &ErrorKind) -> &ErrorKind {
+    match err {
+        ErrorKind::Context(inner, _) => innermost(inner),
+        other => other,
+    }
+}
+
+/// A push-based decoder for FIT data arriving in chunks, e.g. from a socket or a file
+/// being read incrementally.
+///
+/// Feed bytes in as they arrive with [`feed`](StreamingFitDecoder::feed), then drain
+/// whatever records are ready with [`next_record`](StreamingFitDecoder::next_record).
+/// Definition messages and developer field definitions persist across chunk boundaries in
+/// `state`, and the CRC is accumulated incrementally so the final check still applies once
+/// the documented data size has been consumed.
+#[derive(Debug, Default)]
+pub struct StreamingFitDecoder {
+    buf: Vec<u8>,
+    state: DecoderState,
+    ready: std::collections::VecDeque<FitDataRecord>,
+    /// Total `buf` length that must be reached before another parse attempt is worth
+    /// making, or `0` if the next `feed` should always retry. Set from the `Needed` hint
+    /// of the last failed attempt *relative to `buf`'s length at that time* (`buf.len() +
+    /// n`), not as a standalone "bytes still needed" count, since `buf` already shrinks by
+    /// the amount consumed on every successful parse.
+    needed: usize,
+}
+
+impl StreamingFitDecoder {
+    /// Create an empty decoder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append newly-received bytes and decode as many complete records as possible.
+    ///
+    /// Returns `Ok(())` as long as decoding can continue to make progress or is simply
+    /// waiting on more data; returns `Err` for any other error, recoverable ones such as
+    /// `InvalidCrc` and `TrailingBytes` included.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<()> {
+        self.buf.extend_from_slice(chunk);
+        if self.buf.len() < self.needed {
+            // Not enough new data has arrived yet to be worth another parse attempt.
+            return Ok(());
+        }
+
+        loop {
+            if self.buf.is_empty() {
+                self.needed = 0;
+                break;
+            }
+            match decode_next(&mut self.state, &self.buf) {
+                Ok((obj, rest)) => {
+                    if let FitObject::Data(record) = obj {
+                        self.ready.push_back(record);
+                    }
+                    let consumed = self.buf.len() - rest.len();
+                    self.buf.drain(..consumed);
+                    self.needed = 0;
+                }
+                Err(err) => match innermost(&err) {
+                    ErrorKind::UnexpectedEof(nom::Needed::Size(n)) => {
+                        self.needed = self.buf.len() + n.get();
+                        break;
+                    }
+                    ErrorKind::UnexpectedEof(nom::Needed::Unknown) => {
+                        // No size hint is available; wait for at least one more byte.
+                        self.needed = self.buf.len() + 1;
+                        break;
+                    }
+                    _ => return Err(err),
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove and return the next fully-decoded record, if one is ready.
+    pub fn next_record(&mut self) -> Option<FitDataRecord> {
+        self.ready.pop_front()
+    }
+}
+
+impl Iterator for StreamingFitDecoder {
+    type Item = FitDataRecord;
+
+    fn next(&mut self) -> Option<FitDataRecord> {
+        self.next_record()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_fit_file(field_value: u8) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&[0x40, 0x00, 0x00, 20, 0x00, 0x01, 0x02, 0x01, 0x02]);
+        body.extend_from_slice(&[0x00, field_value]);
+
+        let mut header = vec![12u8, 0x10, 0x00, 0x00];
+        header.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        header.extend_from_slice(b".FIT");
+
+        let mut file = header.clone();
+        file.extend_from_slice(&body);
+
+        let mut crc = crate::de::crc16(&header[..12], 0);
+        crc = crate::de::crc16(&body, crc);
+        file.extend_from_slice(&crc.to_le_bytes());
+        file
+    }
+
+    #[test]
+    fn decodes_a_record_split_across_chunks() {
+        let file = build_fit_file(7);
+        let mut decoder = StreamingFitDecoder::new();
+
+        // Split just before the single data message's field byte.
+        let split = file.len() - 3;
+        decoder.feed(&file[..split]).unwrap();
+        assert!(decoder.next_record().is_none());
+
+        decoder.feed(&file[split..]).unwrap();
+        let record = decoder.next_record().expect("record should be ready now");
+        assert_eq!(record.kind, "record");
+        assert_eq!(record.fields[0].value, vec![7]);
+    }
+
+    #[test]
+    fn waits_for_the_exact_hinted_byte_count() {
+        let file = build_fit_file(9);
+        let mut decoder = StreamingFitDecoder::new();
+
+        // Feed one byte at a time; a `needed` miscomputation that treats the hint as a
+        // buffer-total rather than relative to the point of failure would stall forever
+        // or accept a parse attempt before enough bytes are present.
+        // One byte of the 12-byte header hints 11 more, i.e. a 12-byte buffer.
+        decoder.feed(&file[..1]).unwrap();
+        assert_eq!(decoder.needed, 12);
+        for byte in &file[1..] {
+            decoder.feed(std::slice::from_ref(byte)).unwrap();
+        }
+        let record = decoder
+            .next_record()
+            .expect("record should decode eventually");
+        assert_eq!(record.fields[0].value, vec![9]);
+    }
+}